@@ -0,0 +1,135 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read, Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use binstalk_types::cargo_toml_binstall::{PkgFmtDecomposed, TarBasedFmt};
+use bzip2::read::BzDecoder;
+use flate2::read::GzDecoder;
+use xz2::read::XzDecoder;
+use zstd::stream::read::Decoder as ZstdDecoder;
+
+/// Errors that can occur while extracting a downloaded package into
+/// `output_dir`.
+#[derive(Debug, thiserror::Error)]
+pub enum ExtractError {
+    #[error("failed to create `{0}`: {1}")]
+    CreateDir(PathBuf, #[source] io::Error),
+
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    #[error(transparent)]
+    Zip(#[from] zip::result::ZipError),
+
+    #[error("failed to extract 7z archive: {0}")]
+    SevenZip(String),
+
+    #[error("failed to decompress lzma stream: {0}")]
+    Lzma(String),
+
+    #[error("no entry named `{0}` found in archive")]
+    BinNotFound(String),
+}
+
+/// Extract a downloaded package of format `fmt` from `dat` into
+/// `output_dir`, writing the single expected binary as `bin_name` where
+/// the format isn't a directory-tree archive.
+pub fn extract_archive(
+    fmt: PkgFmtDecomposed,
+    dat: impl Read + Seek,
+    output_dir: &Path,
+    bin_name: &str,
+) -> Result<(), ExtractError> {
+    fs::create_dir_all(output_dir)
+        .map_err(|err| ExtractError::CreateDir(output_dir.to_owned(), err))?;
+
+    match fmt {
+        PkgFmtDecomposed::Tar(fmt) => extract_tar_based_stream(fmt, dat, output_dir)?,
+        PkgFmtDecomposed::Bin => extract_bin(dat, &output_dir.join(bin_name))?,
+        PkgFmtDecomposed::Gz => extract_bin(GzDecoder::new(dat), &output_dir.join(bin_name))?,
+        PkgFmtDecomposed::Bz2 => extract_bin(BzDecoder::new(dat), &output_dir.join(bin_name))?,
+        PkgFmtDecomposed::Xz => extract_bin(XzDecoder::new(dat), &output_dir.join(bin_name))?,
+        PkgFmtDecomposed::Zst => extract_bin(ZstdDecoder::new(dat)?, &output_dir.join(bin_name))?,
+        PkgFmtDecomposed::Lzma => extract_lzma(dat, &output_dir.join(bin_name))?,
+        PkgFmtDecomposed::Zip => extract_zip(dat, output_dir)?,
+        PkgFmtDecomposed::SevenZip => extract_sevenzip(dat, output_dir)?,
+        PkgFmtDecomposed::Ar => extract_ar(dat, output_dir, bin_name)?,
+    }
+
+    Ok(())
+}
+
+fn extract_tar_based_stream(
+    fmt: TarBasedFmt,
+    dat: impl Read,
+    output_dir: &Path,
+) -> Result<(), ExtractError> {
+    match fmt {
+        TarBasedFmt::Tar => tar::Archive::new(dat).unpack(output_dir)?,
+        TarBasedFmt::Tbz2 => tar::Archive::new(BzDecoder::new(dat)).unpack(output_dir)?,
+        TarBasedFmt::Tgz => tar::Archive::new(GzDecoder::new(dat)).unpack(output_dir)?,
+        TarBasedFmt::Txz => tar::Archive::new(XzDecoder::new(dat)).unpack(output_dir)?,
+        TarBasedFmt::Tzstd => tar::Archive::new(ZstdDecoder::new(dat)?).unpack(output_dir)?,
+        TarBasedFmt::Tlzma => {
+            let mut decompressed = Vec::new();
+            lzma_decompress(dat, &mut decompressed)?;
+            tar::Archive::new(decompressed.as_slice()).unpack(output_dir)?
+        }
+    }
+
+    Ok(())
+}
+
+/// Copy a single-file, already-decompressed stream to `dst`.
+fn extract_bin(mut dat: impl Read, dst: &Path) -> Result<(), ExtractError> {
+    let mut out = File::create(dst)?;
+    io::copy(&mut dat, &mut out)?;
+    Ok(())
+}
+
+fn extract_lzma(dat: impl Read, dst: &Path) -> Result<(), ExtractError> {
+    let mut decompressed = Vec::new();
+    lzma_decompress(dat, &mut decompressed)?;
+
+    let mut out = File::create(dst)?;
+    out.write_all(&decompressed)?;
+    Ok(())
+}
+
+/// Decompress a raw LZMA1 stream, as opposed to the XZ container format
+/// handled by [`XzDecoder`].
+fn lzma_decompress(mut dat: impl Read, out: &mut Vec<u8>) -> Result<(), ExtractError> {
+    lzma_rs::lzma_decompress(&mut io::BufReader::new(&mut dat), out)
+        .map_err(|err| ExtractError::Lzma(err.to_string()))
+}
+
+fn extract_zip(dat: impl Read + Seek, output_dir: &Path) -> Result<(), ExtractError> {
+    zip::ZipArchive::new(dat)?.extract(output_dir)?;
+    Ok(())
+}
+
+/// 7z is, like zip, a container that may hold a single binary or a whole
+/// directory tree, so it is extracted wholesale the same way.
+fn extract_sevenzip(dat: impl Read + Seek, output_dir: &Path) -> Result<(), ExtractError> {
+    sevenz_rust::decompress(dat, output_dir)
+        .map_err(|err| ExtractError::SevenZip(err.to_string()))
+}
+
+/// Unlike zip/7z, a plain `ar` archive has no notion of a directory tree,
+/// so its members are matched against `bin_name` and only that entry is
+/// written out.
+fn extract_ar(dat: impl Read, output_dir: &Path, bin_name: &str) -> Result<(), ExtractError> {
+    let mut archive = ar::Archive::new(dat);
+
+    while let Some(entry) = archive.next_entry() {
+        let mut entry = entry?;
+
+        if entry.header().identifier() == bin_name.as_bytes() {
+            return extract_bin(&mut entry, &output_dir.join(bin_name));
+        }
+    }
+
+    Err(ExtractError::BinNotFound(bin_name.to_string()))
+}
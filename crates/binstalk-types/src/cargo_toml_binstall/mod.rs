@@ -0,0 +1,5 @@
+mod package_formats;
+
+pub use package_formats::{
+    split_filename_and_extensions, ParsePkgFmtError, PkgFmt, PkgFmtDecomposed, TarBasedFmt,
+};
@@ -1,12 +1,11 @@
+use std::{fmt, str::FromStr};
+
 use serde::{Deserialize, Serialize};
-use strum_macros::{Display, EnumIter, EnumString};
+use strum_macros::{Display, EnumIter};
 
 /// Binary format enumeration
-#[derive(
-    Debug, Display, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, EnumString, EnumIter,
-)]
+#[derive(Debug, Display, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, EnumIter)]
 #[serde(rename_all = "snake_case")]
-#[strum(ascii_case_insensitive)]
 pub enum PkgFmt {
     /// Download format is TAR (uncompressed)
     Tar,
@@ -26,8 +25,16 @@ pub enum PkgFmt {
     Tzstd,
     /// Download format is ZST (zstd)
     Zst,
+    /// Download format is TAR + LZMA
+    Tlzma,
+    /// Download format is LZMA
+    Lzma,
     /// Download format is Zip
     Zip,
+    /// Download format is 7z
+    SevenZip,
+    /// Download format is a Unix `ar` archive
+    Ar,
     /// Download format is raw / binary
     Bin,
 }
@@ -38,6 +45,76 @@ impl Default for PkgFmt {
     }
 }
 
+/// Error returned when a `--pkg-fmt`-style string doesn't name a known
+/// [`PkgFmt`].
+#[derive(Debug)]
+pub struct ParsePkgFmtError(String);
+
+impl fmt::Display for ParsePkgFmtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}' is not a recognized pkg-fmt", self.0)
+    }
+}
+
+impl std::error::Error for ParsePkgFmtError {}
+
+impl FromStr for PkgFmt {
+    type Err = ParsePkgFmtError;
+
+    /// Parses a `--pkg-fmt`-style string, tolerating case, `.`/`-`/`|`/`+`/
+    /// whitespace as separators between a `tar` prefix and its codec, and a
+    /// handful of aliases release toolchains commonly use in their place
+    /// (`gzip`, `zstd`, `bzip2`), in addition to the exact snake_case
+    /// variant names.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParsePkgFmtError(s.to_string());
+
+        let normalized = s
+            .trim()
+            .to_ascii_lowercase()
+            .replace(['.', '-', '|', '+'], " ");
+        let mut parts = normalized.split_whitespace();
+
+        let first = parts.next().ok_or_else(invalid)?;
+        let (is_tar, codec) = if first == "tar" {
+            (true, parts.next())
+        } else {
+            (false, Some(first))
+        };
+
+        // No more than "tar" + one codec is ever valid.
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+
+        Ok(match (is_tar, codec) {
+            (false, Some("bin") | Some("exe")) => PkgFmt::Bin,
+            (false, Some("zip")) => PkgFmt::Zip,
+            (false, Some("7z") | Some("sevenzip") | Some("seven_zip")) => PkgFmt::SevenZip,
+            (false, Some("ar")) => PkgFmt::Ar,
+            (false, Some("gz") | Some("gzip")) => PkgFmt::Gz,
+            (false, Some("bz2") | Some("bzip2")) => PkgFmt::Bz2,
+            (false, Some("xz")) => PkgFmt::Xz,
+            (false, Some("zst") | Some("zstd")) => PkgFmt::Zst,
+            (false, Some("lzma")) => PkgFmt::Lzma,
+            (false, Some("tgz")) => PkgFmt::Tgz,
+            (false, Some("tbz2")) => PkgFmt::Tbz2,
+            (false, Some("txz")) => PkgFmt::Txz,
+            (false, Some("tzst") | Some("tzstd")) => PkgFmt::Tzstd,
+            (false, Some("tlzma")) => PkgFmt::Tlzma,
+
+            (true, None) => PkgFmt::Tar,
+            (true, Some("gz") | Some("gzip")) => PkgFmt::Tgz,
+            (true, Some("bz2") | Some("bzip2")) => PkgFmt::Tbz2,
+            (true, Some("xz")) => PkgFmt::Txz,
+            (true, Some("zst") | Some("zstd")) => PkgFmt::Tzstd,
+            (true, Some("lzma")) => PkgFmt::Tlzma,
+
+            _ => return Err(invalid()),
+        })
+    }
+}
+
 impl PkgFmt {
     /// If self is one of the tar based formats, return Some.
     pub fn decompose(self) -> PkgFmtDecomposed {
@@ -51,8 +128,12 @@ impl PkgFmt {
             PkgFmt::Xz => PkgFmtDecomposed::Xz,
             PkgFmt::Tzstd => PkgFmtDecomposed::Tar(TarBasedFmt::Tzstd),
             PkgFmt::Zst => PkgFmtDecomposed::Zst,
+            PkgFmt::Tlzma => PkgFmtDecomposed::Tar(TarBasedFmt::Tlzma),
+            PkgFmt::Lzma => PkgFmtDecomposed::Lzma,
             PkgFmt::Bin => PkgFmtDecomposed::Bin,
             PkgFmt::Zip => PkgFmtDecomposed::Zip,
+            PkgFmt::SevenZip => PkgFmtDecomposed::SevenZip,
+            PkgFmt::Ar => PkgFmtDecomposed::Ar,
         }
     }
 
@@ -72,6 +153,8 @@ impl PkgFmt {
             PkgFmt::Xz => &[".xz"],
             PkgFmt::Tzstd => &[".tzstd", ".tzst", ".tar.zst"],
             PkgFmt::Zst => &[".zst"],
+            PkgFmt::Tlzma => &[".tar.lzma", ".tlzma"],
+            PkgFmt::Lzma => &[".lzma"],
             PkgFmt::Bin => {
                 if is_windows {
                     &[".bin", "", ".exe"]
@@ -80,54 +163,97 @@ impl PkgFmt {
                 }
             }
             PkgFmt::Zip => &[".zip"],
+            PkgFmt::SevenZip => &[".7z"],
+            PkgFmt::Ar => &[".ar"],
         }
     }
 
     /// Given the pkg-url template, guess the possible pkg-fmt.
     pub fn guess_pkg_format(pkg_url: &str) -> Option<Self> {
-        let mut it = pkg_url.rsplitn(3, '.');
+        split_filename_and_extensions(pkg_url).1
+    }
+}
 
-        let guess = match it.next()? {
-            "tar" => Some(PkgFmt::Tar),
+/// Lowercase `name` and peel off its trailing extension tokens (if any are
+/// recognized), returning what's left of the name along with the [`PkgFmt`]
+/// implied by the extension(s) that were peeled off.
+///
+/// A trailing compression token (`gz`, `bz2`, `xz`, `zst`, `lzma`) sitting
+/// on top of a `tar` token is folded into the corresponding fused tar
+/// variant, e.g.
+/// `archive.tar.gz` yields `("archive", Some(PkgFmt::Tgz))`. This is used
+/// both by [`PkgFmt::guess_pkg_format`] and by callers that need the
+/// remaining stem to match it against an expected binary name.
+pub fn split_filename_and_extensions(name: &str) -> (String, Option<PkgFmt>) {
+    let mut stem = name.to_ascii_lowercase();
 
-            "tbz2" => Some(PkgFmt::Tbz2),
-            "bz2" => Some(PkgFmt::Bz2),
+    // If there's no dot, treat the whole name as a bare extension, same
+    // as `name.rsplitn(2, '.').next()` would.
+    let (rest_len, ext) = match stem.rsplit_once('.') {
+        Some((rest, ext)) => (rest.len(), ext.to_string()),
+        None => (0, stem.clone()),
+    };
+    let ext = ext.as_str();
 
-            "tgz" => Some(PkgFmt::Tgz),
-            "gz" => Some(PkgFmt::Gz),
+    let fmt = match ext {
+        "tar" => Some(PkgFmt::Tar),
 
-            "txz" => Some(PkgFmt::Txz),
-            "xz" => Some(PkgFmt::Xz),
+        "tbz2" => Some(PkgFmt::Tbz2),
+        "bz2" => Some(PkgFmt::Bz2),
 
-            "tzstd" | "tzst" => Some(PkgFmt::Tzstd),
-            "zst" => Some(PkgFmt::Zst),
+        "tgz" => Some(PkgFmt::Tgz),
+        "gz" => Some(PkgFmt::Gz),
 
-            "exe" | "bin" => Some(PkgFmt::Bin),
-            "zip" => Some(PkgFmt::Zip),
+        "txz" => Some(PkgFmt::Txz),
+        "xz" => Some(PkgFmt::Xz),
 
-            _ => None,
-        };
+        "tzstd" | "tzst" => Some(PkgFmt::Tzstd),
+        "zst" => Some(PkgFmt::Zst),
 
-        // If we have a guess, and our next segment is "tar"...
-        if guess.is_some() && it.next() == Some("tar") {
-            // ...And if there's another segment before it...
-            if it.next().is_some() {
-                // ...then we have a `.tar.{fmt}`, so we convert our guess a tar-based format
-                guess.map(|pkgfmt| match pkgfmt {
-                    PkgFmt::Bz2 => PkgFmt::Tbz2,
-                    PkgFmt::Gz => PkgFmt::Tgz,
-                    PkgFmt::Xz => PkgFmt::Txz,
-                    PkgFmt::Zst => PkgFmt::Tzstd,
-                    _ => pkgfmt,
-                })
-            } else {
-                // Otherwise, we can assume our pkg_url to be malformed
-                None
-            }
-        } else {
-            // Otherwise, assume our guess is correct.
-            guess
+        "tlzma" => Some(PkgFmt::Tlzma),
+        "lzma" => Some(PkgFmt::Lzma),
+
+        "exe" | "bin" => Some(PkgFmt::Bin),
+        "zip" => Some(PkgFmt::Zip),
+        "7z" => Some(PkgFmt::SevenZip),
+        "ar" => Some(PkgFmt::Ar),
+
+        _ => None,
+    };
+
+    let Some(fmt) = fmt else {
+        return (stem, None);
+    };
+
+    stem.truncate(rest_len);
+
+    // If our guess is a bare compression format...
+    if !matches!(
+        fmt,
+        PkgFmt::Bz2 | PkgFmt::Gz | PkgFmt::Xz | PkgFmt::Zst | PkgFmt::Lzma
+    ) {
+        return (stem, Some(fmt));
+    }
+
+    match stem.rsplit_once('.') {
+        // ...and it sits directly on top of a "tar" extension, fold it
+        // into the fused tar-based format.
+        Some((rest, "tar")) => {
+            let rest_len = rest.len();
+            stem.truncate(rest_len);
+            let fmt = match fmt {
+                PkgFmt::Bz2 => PkgFmt::Tbz2,
+                PkgFmt::Gz => PkgFmt::Tgz,
+                PkgFmt::Xz => PkgFmt::Txz,
+                PkgFmt::Zst => PkgFmt::Tzstd,
+                PkgFmt::Lzma => PkgFmt::Tlzma,
+                _ => unreachable!(),
+            };
+            (stem, Some(fmt))
         }
+        // "tar.{fmt}" with nothing before "tar" is a malformed pkg_url.
+        None if stem == "tar" => (stem, None),
+        _ => (stem, Some(fmt)),
     }
 }
 
@@ -138,8 +264,11 @@ pub enum PkgFmtDecomposed {
     Gz,
     Xz,
     Zst,
+    Lzma,
     Bin,
     Zip,
+    SevenZip,
+    Ar,
 }
 
 #[derive(Debug, Display, Copy, Clone, Eq, PartialEq)]
@@ -154,6 +283,8 @@ pub enum TarBasedFmt {
     Txz,
     /// Download format is TAR + Zstd
     Tzstd,
+    /// Download format is TAR + LZMA
+    Tlzma,
 }
 
 impl From<TarBasedFmt> for PkgFmt {
@@ -164,6 +295,86 @@ impl From<TarBasedFmt> for PkgFmt {
             TarBasedFmt::Tgz => PkgFmt::Tgz,
             TarBasedFmt::Txz => PkgFmt::Txz,
             TarBasedFmt::Tzstd => PkgFmt::Tzstd,
+            TarBasedFmt::Tlzma => PkgFmt::Tlzma,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_str_accepts_exact_snake_case_names() {
+        let cases = [
+            (PkgFmt::Tar, "tar"),
+            (PkgFmt::Tbz2, "tbz2"),
+            (PkgFmt::Bz2, "bz2"),
+            (PkgFmt::Tgz, "tgz"),
+            (PkgFmt::Gz, "gz"),
+            (PkgFmt::Txz, "txz"),
+            (PkgFmt::Xz, "xz"),
+            (PkgFmt::Tzstd, "tzstd"),
+            (PkgFmt::Zst, "zst"),
+            (PkgFmt::Tlzma, "tlzma"),
+            (PkgFmt::Lzma, "lzma"),
+            (PkgFmt::Zip, "zip"),
+            (PkgFmt::SevenZip, "seven_zip"),
+            (PkgFmt::Ar, "ar"),
+            (PkgFmt::Bin, "bin"),
+        ];
+
+        for (fmt, name) in cases {
+            assert_eq!(name.parse::<PkgFmt>().unwrap(), fmt, "failed for {name:?}");
+        }
+    }
+
+    #[test]
+    fn from_str_accepts_aliases_and_tar_fusions() {
+        assert_eq!("tar.gz".parse::<PkgFmt>().unwrap(), PkgFmt::Tgz);
+        assert_eq!("TAR-GZIP".parse::<PkgFmt>().unwrap(), PkgFmt::Tgz);
+        assert_eq!("tar|zstd".parse::<PkgFmt>().unwrap(), PkgFmt::Tzstd);
+        assert_eq!("tar.lzma".parse::<PkgFmt>().unwrap(), PkgFmt::Tlzma);
+        assert_eq!("bzip2".parse::<PkgFmt>().unwrap(), PkgFmt::Bz2);
+        assert_eq!("7z".parse::<PkgFmt>().unwrap(), PkgFmt::SevenZip);
+        assert!("bogus".parse::<PkgFmt>().is_err());
+        assert!("tar.bogus".parse::<PkgFmt>().is_err());
+    }
+
+    #[test]
+    fn split_filename_and_extensions_handles_bare_and_fused_formats() {
+        assert_eq!(
+            split_filename_and_extensions("foo.tar.gz"),
+            ("foo".to_string(), Some(PkgFmt::Tgz))
+        );
+        assert_eq!(
+            split_filename_and_extensions("FOO.TGZ"),
+            ("foo".to_string(), Some(PkgFmt::Tgz))
+        );
+        assert_eq!(
+            split_filename_and_extensions("foo.gz"),
+            ("foo".to_string(), Some(PkgFmt::Gz))
+        );
+        assert_eq!(
+            split_filename_and_extensions("foo.tar.lzma"),
+            ("foo".to_string(), Some(PkgFmt::Tlzma))
+        );
+        assert_eq!(
+            split_filename_and_extensions("app.7z"),
+            ("app".to_string(), Some(PkgFmt::SevenZip))
+        );
+        assert_eq!(
+            split_filename_and_extensions("APP.AR"),
+            ("app".to_string(), Some(PkgFmt::Ar))
+        );
+        // "tar.gz" with nothing before "tar" is a malformed pkg_url.
+        assert_eq!(
+            split_filename_and_extensions("tar.gz"),
+            ("tar".to_string(), None)
+        );
+        assert_eq!(
+            split_filename_and_extensions("binary"),
+            ("binary".to_string(), None)
+        );
+    }
+}
@@ -0,0 +1 @@
+pub mod cargo_toml_binstall;